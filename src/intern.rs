@@ -0,0 +1,137 @@
+//! Global, opt-in structural interner for [`crate::node::Node`]s. Nodes are keyed by
+//! their content hash, so two unrelated [`crate::trie::Trie`] snapshots that happen to
+//! contain identical subtrees collapse to a single `Arc` allocation instead of holding
+//! separate copies. Entries are stored weakly, and dead ones are periodically swept out
+//! of the map itself: a COW trie under normal put/delete churn produces a new, essentially
+//! unique hash on almost every mutation, so without sweeping the map would grow by one
+//! permanent slot per historical node for the life of the process.
+
+use crate::node::Node;
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use std::any::{Any, TypeId};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, LazyLock, Weak};
+
+type NodeHash = [u8; 32];
+
+/// How many inserts to allow between sweeps of dead entries. Sweeping on every insert
+/// would make `intern` pay for a full map scan per call; amortizing it over a batch keeps
+/// the common case (an already-cached, still-alive node) cheap.
+const SWEEP_INTERVAL: usize = 1024;
+
+/// Object-safe handle to a `Weak<Node<V>>` for some erased `V`, letting the interner check
+/// liveness and downcast back to the concrete type without knowing `V` ahead of time.
+trait ErasedWeak: Any + Send + Sync {
+    fn as_any(&self) -> &dyn Any;
+    fn is_alive(&self) -> bool;
+}
+
+impl<V: Send + Sync + 'static> ErasedWeak for Weak<Node<V>> {
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    fn is_alive(&self) -> bool {
+        self.strong_count() > 0
+    }
+}
+
+static INTERNER: LazyLock<DashMap<(TypeId, NodeHash), Box<dyn ErasedWeak>>> =
+    LazyLock::new(DashMap::new);
+
+static INSERTS_SINCE_SWEEP: AtomicUsize = AtomicUsize::new(0);
+
+/// Drops every entry whose `Weak` has no surviving `Arc`, reclaiming the map slots that
+/// entries left behind once their last `Trie` was dropped.
+fn sweep() {
+    INTERNER.retain(|_, weak| weak.is_alive());
+}
+
+/// Bumps the insert counter and sweeps dead entries once it crosses [`SWEEP_INTERVAL`].
+fn maybe_sweep() {
+    if INSERTS_SINCE_SWEEP.fetch_add(1, Ordering::Relaxed) + 1 >= SWEEP_INTERVAL {
+        INSERTS_SINCE_SWEEP.store(0, Ordering::Relaxed);
+        sweep();
+    }
+}
+
+/// Returns the interned `Arc<Node<V>>` for `hash`, reusing an existing allocation if one
+/// with the same content hash (and value type) is already cached and still alive.
+/// Otherwise `build` is run to construct the node, which is then interned for future
+/// callers to share. The whole lookup-or-build-or-insert happens under the map's shard
+/// lock for this key, so two threads racing to intern the same new content can't both
+/// win: only one `build()` result is ever kept, and every caller observes the same `Arc`.
+pub fn intern<V: Send + Sync + 'static>(
+    hash: NodeHash,
+    build: impl FnOnce() -> Node<V>,
+) -> Arc<Node<V>> {
+    let key = (TypeId::of::<V>(), hash);
+
+    match INTERNER.entry(key) {
+        Entry::Occupied(mut entry) => {
+            if let Some(existing) = entry
+                .get()
+                .as_any()
+                .downcast_ref::<Weak<Node<V>>>()
+                .and_then(Weak::upgrade)
+            {
+                return existing;
+            }
+            let node = Arc::new(build());
+            entry.insert(Box::new(Arc::downgrade(&node)));
+            // Release this shard's lock first: sweeping locks every shard.
+            drop(entry);
+            maybe_sweep();
+            node
+        }
+        Entry::Vacant(entry) => {
+            let node = Arc::new(build());
+            entry.insert(Box::new(Arc::downgrade(&node)));
+            maybe_sweep();
+            node
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+    use std::thread;
+
+    #[test]
+    fn concurrent_intern_of_identical_content_collapses_to_one_allocation() {
+        // A type local to this test, so its `TypeId` can't collide with content hashes
+        // any other test interns under `Value` or another shared type.
+        #[derive(Clone, Debug, PartialEq)]
+        struct Payload(u32);
+
+        const THREAD_COUNT: usize = 8;
+        let hash = [42u8; 32];
+        let barrier = Arc::new(Barrier::new(THREAD_COUNT));
+
+        let handles: Vec<_> = (0..THREAD_COUNT)
+            .map(|_| {
+                let barrier = Arc::clone(&barrier);
+                thread::spawn(move || {
+                    barrier.wait();
+                    intern::<Payload>(hash, || {
+                        let mut node = Node::new();
+                        node.value = Some(Payload(7));
+                        node.hash = hash;
+                        node
+                    })
+                })
+            })
+            .collect();
+
+        let roots: Vec<Arc<Node<Payload>>> =
+            handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+        for root in &roots[1..] {
+            assert!(Arc::ptr_eq(&roots[0], root));
+        }
+        assert_eq!(roots[0].value, Some(Payload(7)));
+    }
+}
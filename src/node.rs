@@ -1,18 +1,77 @@
-use crate::value::Value;
+use crate::hash::sha256;
 use std::collections::HashMap;
 use std::sync::Arc;
 
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A value type that can be content-addressed. Required by [`Node::compute_hash`] so the
+/// Merkle hash can be computed for any stored value, not just the built-in [`crate::value::Value`].
+pub trait HashableValue {
+    /// Canonical byte encoding of this value, used as hash input.
+    fn hash_bytes(&self) -> Vec<u8>;
+}
+
+/// An outgoing edge of a [`Node`]: a compressed path segment (the label, as raw key
+/// bytes) leading to the child at its end. Collapsing runs of single-child nodes into
+/// one edge is what makes this a radix trie instead of a plain byte trie.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Edge<V> {
+    pub label: Vec<u8>,
+    pub target: Arc<Node<V>>,
+}
+
 #[derive(Clone, Debug, PartialEq)]
-pub struct Node {
-    pub value: Option<Value>,
-    pub children: HashMap<char, Arc<Node>>,
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct Node<V> {
+    pub value: Option<V>,
+    pub children: HashMap<u8, Edge<V>>,
+    /// Merkle hash of this node: `H(value_bytes || sorted edges)`. Children are hashed
+    /// bottom-up as they're built, so recomputing this is O(number of children), not
+    /// O(subtree size).
+    pub hash: [u8; 32],
 }
 
-impl Node {
+impl<V> Node<V> {
     pub fn new() -> Self {
         Node {
             value: None,
             children: HashMap::new(),
+            hash: [0u8; 32],
+        }
+    }
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V: HashableValue> Node<V> {
+    /// Recomputes this node's hash from its current `value` and `children`. Callers
+    /// building a new node must call this (and assign the result to `hash`) after
+    /// setting `value`/`children` and before sharing the node behind an `Arc`.
+    pub fn compute_hash(&self) -> [u8; 32] {
+        let mut bytes = match &self.value {
+            Some(value) => {
+                let mut b = vec![1u8];
+                b.extend(value.hash_bytes());
+                b
+            }
+            None => vec![0u8],
+        };
+
+        let mut edges: Vec<(&u8, &Edge<V>)> = self.children.iter().collect();
+        edges.sort_by_key(|(first_byte, _)| **first_byte);
+
+        for (_, edge) in edges {
+            bytes.extend_from_slice(&(edge.label.len() as u32).to_le_bytes());
+            bytes.extend_from_slice(&edge.label);
+            bytes.extend_from_slice(&edge.target.hash);
         }
+
+        sha256(&bytes)
     }
 }
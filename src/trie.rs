@@ -1,160 +1,611 @@
-use crate::node::Node;
+use crate::intern;
+use crate::node::{Edge, HashableValue, Node};
 use crate::value::Value;
 use std::sync::Arc;
 
+/// A key type that can be encoded as a byte sequence for storage in a [`Trie`]. Following
+/// the `TrieKey` convention used by general-purpose radix-trie crates, this is the single
+/// hook needed to key a trie by strings, raw bytes, or any caller-defined token type.
+pub trait TrieKey {
+    fn encode_bytes(&self) -> Vec<u8>;
+}
+
+impl TrieKey for str {
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl TrieKey for String {
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+impl TrieKey for [u8] {
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.to_vec()
+    }
+}
+
+impl TrieKey for Vec<u8> {
+    fn encode_bytes(&self) -> Vec<u8> {
+        self.clone()
+    }
+}
+
+/// Length of the longest common prefix of `a` and `b`, in bytes.
+fn common_prefix_len(a: &[u8], b: &[u8]) -> usize {
+    a.iter().zip(b.iter()).take_while(|(x, y)| x == y).count()
+}
+
+/// A node-construction hook: when present, every node a [`Trie`] builds is routed through
+/// it instead of a plain `Arc::new`. See [`Trie::with_interning`] and [`intern_alloc`].
+type Allocator<V> = Option<fn(Node<V>) -> Arc<Node<V>>>;
+
+/// A copy-on-write, content-addressed radix trie. Generic over the stored value type `V`;
+/// defaults to [`Value`] so existing callers keep using plain `Trie` unchanged. Keys are
+/// accepted as any `&K where K: TrieKey + ?Sized` (e.g. `&str`, `&[u8]`), so the same
+/// structure keys binary workloads as readily as string ones.
 #[derive(Clone)]
-pub struct Trie {
-    root: Option<Arc<Node>>,
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "serde",
+    serde(bound(
+        serialize = "V: serde::Serialize",
+        deserialize = "V: serde::de::DeserializeOwned"
+    ))
+)]
+pub struct Trie<V = Value> {
+    root: Option<Arc<Node<V>>>,
+    /// When set, every node this snapshot (and its descendants) builds is routed through
+    /// this allocator instead of constructed with a plain `Arc::new`. See
+    /// [`Trie::with_interning`]. Not serializable (a function pointer can't round-trip
+    /// through serde), so a deserialized `Trie` always comes back with interning off.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    allocator: Allocator<V>,
 }
 
-impl Trie {
+impl<V> Default for Trie<V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<V> Trie<V> {
     pub fn new() -> Self {
-        Trie { root: None }
+        Trie {
+            root: None,
+            allocator: None,
+        }
+    }
+}
+
+impl<V: Send + Sync + 'static> Trie<V> {
+    /// Like [`Trie::new`], but every node built while growing this snapshot is deduplicated
+    /// against the global structural interner: if an identical node (same content hash)
+    /// already exists anywhere in the process, its `Arc` is reused instead of allocating a
+    /// new one. Useful when many independent snapshots are expected to share subtrees.
+    pub fn with_interning() -> Self {
+        Trie {
+            root: None,
+            allocator: Some(intern_alloc::<V>),
+        }
+    }
+}
+
+/// Default allocator for [`Trie::with_interning`]: routes node construction through the
+/// global structural interner, which is the only thing that needs `V: Send + Sync +
+/// 'static` — plain (non-interning) tries never touch this function and so never need
+/// that bound.
+fn intern_alloc<V: Send + Sync + 'static>(node: Node<V>) -> Arc<Node<V>> {
+    let hash = node.hash;
+    intern::intern(hash, move || node)
+}
+
+impl<V: Clone + HashableValue> Trie<V> {
+    /// Builds the `Arc` for a freshly constructed node, routing it through `allocator`
+    /// when this snapshot was created with [`Trie::with_interning`].
+    fn make_arc(node: Node<V>, allocator: Allocator<V>) -> Arc<Node<V>> {
+        match allocator {
+            Some(alloc) => alloc(node),
+            None => Arc::new(node),
+        }
     }
 
-    pub fn get(&self, key: &str) -> Option<Value> {
+    pub fn get<K: TrieKey + ?Sized>(&self, key: &K) -> Option<V> {
         let root = self.root.as_ref()?;
         let mut current = root;
+        let key = key.encode_bytes();
+        let mut remaining = key.as_slice();
 
-        for c in key.chars() {
-            match current.children.get(&c) {
-                Some(node) => current = node,
-                None => return None,
+        while !remaining.is_empty() {
+            let edge = current.children.get(&remaining[0])?;
+            if !remaining.starts_with(edge.label.as_slice()) {
+                return None;
             }
+            remaining = &remaining[edge.label.len()..];
+            current = &edge.target;
         }
 
         current.value.clone()
     }
 
-    pub fn put(&self, key: &str, value: impl Into<Value>) -> Self {
-        let new_root = if let Some(root) = &self.root {
-            Some(Self::put_helper(
-                root.clone(),
-                key.chars().collect(),
-                0,
-                value,
-            ))
-        } else {
-            let mut new_node = Node::new();
-            if key.is_empty() {
-                new_node.value = Some(value.into());
-            } else {
-                new_node.children.insert(
-                    key.chars().next().unwrap(),
-                    Arc::new(Self::create_path(key.chars().skip(1).collect(), value)),
-                );
-            }
-            Some(Arc::new(new_node))
+    pub fn put<K: TrieKey + ?Sized>(&self, key: &K, value: impl Into<V>) -> Self {
+        let key = key.encode_bytes();
+        let value = value.into();
+        let new_root = match &self.root {
+            Some(root) => Self::put_helper(root, &key, value, self.allocator),
+            None => Self::create_path(&key, value, self.allocator),
         };
 
-        Trie { root: new_root }
+        Trie {
+            root: Some(new_root),
+            allocator: self.allocator,
+        }
     }
 
     fn put_helper(
-        node: Arc<Node>,
-        key: Vec<char>,
-        depth: usize,
-        value: impl Into<Value>,
-    ) -> Arc<Node> {
+        node: &Arc<Node<V>>,
+        key: &[u8],
+        value: V,
+        allocator: Allocator<V>,
+    ) -> Arc<Node<V>> {
         let mut new_node = Node::new();
-
-        // Copy existing value and children
         new_node.value = node.value.clone();
         new_node.children = node.children.clone();
 
-        if depth == key.len() {
-            new_node.value = Some(value.into());
-        } else {
-            let c = key[depth];
-            let child = if let Some(child) = node.children.get(&c) {
-                Self::put_helper(child.clone(), key, depth + 1, value)
-            } else {
-                Arc::new(Self::create_path(
-                    key.into_iter().skip(depth + 1).collect(),
-                    value,
-                ))
-            };
-            new_node.children.insert(c, child);
+        if key.is_empty() {
+            new_node.value = Some(value);
+            new_node.hash = new_node.compute_hash();
+            return Self::make_arc(new_node, allocator);
         }
 
-        Arc::new(new_node)
+        let first = key[0];
+        match node.children.get(&first) {
+            None => {
+                new_node.children.insert(
+                    first,
+                    Edge {
+                        label: key.to_vec(),
+                        target: Self::create_path(&[], value, allocator),
+                    },
+                );
+            }
+            Some(edge) => {
+                let common = common_prefix_len(key, &edge.label);
+
+                if common == edge.label.len() {
+                    // The key runs through this whole edge; recurse into the child with
+                    // whatever of the key remains.
+                    let new_target =
+                        Self::put_helper(&edge.target, &key[common..], value, allocator);
+                    new_node.children.insert(
+                        first,
+                        Edge {
+                            label: edge.label.clone(),
+                            target: new_target,
+                        },
+                    );
+                } else {
+                    // The key diverges partway through the edge label: split it into a
+                    // shared-prefix node with two children (the classic radix split).
+                    let shared = edge.label[..common].to_vec();
+                    let old_suffix = edge.label[common..].to_vec();
+                    let old_first = old_suffix[0];
+
+                    let mut split_node = Node::new();
+                    split_node.children.insert(
+                        old_first,
+                        Edge {
+                            label: old_suffix,
+                            target: edge.target.clone(),
+                        },
+                    );
+
+                    if common == key.len() {
+                        split_node.value = Some(value);
+                    } else {
+                        let new_suffix = key[common..].to_vec();
+                        let new_first = new_suffix[0];
+                        split_node.children.insert(
+                            new_first,
+                            Edge {
+                                label: new_suffix,
+                                target: Self::create_path(&[], value, allocator),
+                            },
+                        );
+                    }
+
+                    split_node.hash = split_node.compute_hash();
+                    new_node.children.insert(
+                        first,
+                        Edge {
+                            label: shared,
+                            target: Self::make_arc(split_node, allocator),
+                        },
+                    );
+                }
+            }
+        }
+
+        new_node.hash = new_node.compute_hash();
+        Self::make_arc(new_node, allocator)
     }
 
-    fn create_path(key: Vec<char>, value: impl Into<Value>) -> Node {
+    /// Builds a fresh subtree holding `value` at the end of `key`, as a single compressed
+    /// edge rather than one node per byte. Both the subtree's root and its leaf (when
+    /// `key` is non-empty) are built through [`Self::make_arc`], so an interning `Trie`
+    /// can dedupe them just like any node built by `put_helper`/`delete_helper`.
+    fn create_path(key: &[u8], value: V, allocator: Allocator<V>) -> Arc<Node<V>> {
         let mut node = Node::new();
         if key.is_empty() {
-            node.value = Some(value.into());
+            node.value = Some(value);
         } else {
+            let mut leaf = Node::new();
+            leaf.value = Some(value);
+            leaf.hash = leaf.compute_hash();
             node.children.insert(
                 key[0],
-                Arc::new(Self::create_path(key[1..].to_vec(), value)),
+                Edge {
+                    label: key.to_vec(),
+                    target: Self::make_arc(leaf, allocator),
+                },
             );
         }
-        node
+        node.hash = node.compute_hash();
+        Self::make_arc(node, allocator)
     }
 
-    pub fn delete(&self, key: &str) -> Self {
+    pub fn delete<K: TrieKey + ?Sized>(&self, key: &K) -> Self {
+        let key = key.encode_bytes();
         let new_root = match &self.root {
             None => None,
-            Some(root) => Self::delete_helper(root.clone(), key.chars().collect(), 0),
+            Some(root) => Self::delete_helper(root, &key, self.allocator),
         };
 
-        Trie { root: new_root }
+        Trie {
+            root: new_root,
+            allocator: self.allocator,
+        }
     }
 
-    fn delete_helper(node: Arc<Node>, key: Vec<char>, depth: usize) -> Option<Arc<Node>> {
-        if depth == key.len() {
+    fn delete_helper(
+        node: &Arc<Node<V>>,
+        key: &[u8],
+        allocator: Allocator<V>,
+    ) -> Option<Arc<Node<V>>> {
+        if key.is_empty() {
             // If this node has children, keep it but remove the value
-            if !node.children.is_empty() {
-                let mut new_node = Node::new();
-                new_node.children = node.children.clone();
-                return Some(Arc::new(new_node));
+            if node.children.is_empty() {
+                return None;
             }
-            return None;
+            let mut new_node = Node::new();
+            new_node.children = node.children.clone();
+            new_node.hash = new_node.compute_hash();
+            return Some(Self::make_arc(new_node, allocator));
         }
 
-        let c = key[depth];
+        let first = key[0];
         let mut new_node = Node::new();
         new_node.value = node.value.clone();
         new_node.children = node.children.clone();
 
-        if let Some(child) = node.children.get(&c) {
-            if let Some(new_child) = Self::delete_helper(child.clone(), key, depth + 1) {
-                new_node.children.insert(c, new_child);
-            } else {
-                new_node.children.remove(&c);
+        if let Some(edge) = node.children.get(&first) {
+            if key.starts_with(edge.label.as_slice()) {
+                let rest = &key[edge.label.len()..];
+                match Self::delete_helper(&edge.target, rest, allocator) {
+                    Some(new_target) => {
+                        new_node.children.insert(
+                            first,
+                            Self::merge_single_child(edge.label.clone(), new_target),
+                        );
+                    }
+                    None => {
+                        new_node.children.remove(&first);
+                    }
+                }
             }
         }
 
         if new_node.children.is_empty() && new_node.value.is_none() {
             None
         } else {
-            Some(Arc::new(new_node))
+            new_node.hash = new_node.compute_hash();
+            Some(Self::make_arc(new_node, allocator))
+        }
+    }
+
+    /// Re-merges a node with its sole remaining child when the node holds no value of its
+    /// own, collapsing the edge pair back into one compressed edge.
+    fn merge_single_child(label: Vec<u8>, target: Arc<Node<V>>) -> Edge<V> {
+        if target.value.is_none() && target.children.len() == 1 {
+            let child_edge = target.children.values().next().unwrap();
+            let mut merged_label = label;
+            merged_label.extend_from_slice(&child_edge.label);
+            return Edge {
+                label: merged_label,
+                target: child_edge.target.clone(),
+            };
+        }
+        Edge { label, target }
+    }
+
+    /// Returns the values of every stored key that is a prefix of `key`, ordered from
+    /// shortest (closest to the root) to longest.
+    pub fn find_prefixes<K: TrieKey + ?Sized>(&self, key: &K) -> Vec<V> {
+        let mut result = Vec::new();
+        let root = match self.root.as_ref() {
+            Some(root) => root,
+            None => return result,
+        };
+
+        let mut current = root;
+        if let Some(value) = &current.value {
+            result.push(value.clone());
+        }
+
+        let key = key.encode_bytes();
+        let mut remaining = key.as_slice();
+        while !remaining.is_empty() {
+            match current.children.get(&remaining[0]) {
+                Some(edge) if remaining.starts_with(edge.label.as_slice()) => {
+                    remaining = &remaining[edge.label.len()..];
+                    current = &edge.target;
+                    if let Some(value) = &current.value {
+                        result.push(value.clone());
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        result
+    }
+
+    /// Returns the value of the deepest stored key that is a prefix of `key`, if any.
+    pub fn find_longest_prefix<K: TrieKey + ?Sized>(&self, key: &K) -> Option<V> {
+        let root = self.root.as_ref()?;
+        let mut current = root;
+        let mut longest = current.value.clone();
+
+        let key = key.encode_bytes();
+        let mut remaining = key.as_slice();
+        while !remaining.is_empty() {
+            match current.children.get(&remaining[0]) {
+                Some(edge) if remaining.starts_with(edge.label.as_slice()) => {
+                    remaining = &remaining[edge.label.len()..];
+                    current = &edge.target;
+                    if current.value.is_some() {
+                        longest = current.value.clone();
+                    }
+                }
+                _ => break,
+            }
+        }
+
+        longest
+    }
+
+    /// Descends to the node at `prefix` (which may land partway through a compressed
+    /// edge) and collects every key/value pair in its subtree, re-attaching `prefix` to
+    /// each key found. Keys are returned as raw bytes since the key type `K` has no
+    /// general way to reconstruct itself from an encoded byte sequence.
+    pub fn keys_with_prefix<K: TrieKey + ?Sized>(&self, prefix: &K) -> Vec<(Vec<u8>, V)> {
+        let mut result = Vec::new();
+        let root = match self.root.as_ref() {
+            Some(root) => root,
+            None => return result,
+        };
+
+        let mut current = root;
+        let prefix = prefix.encode_bytes();
+        let mut remaining = prefix.as_slice();
+        let mut path = Vec::new();
+
+        while !remaining.is_empty() {
+            match current.children.get(&remaining[0]) {
+                Some(edge) if edge.label.starts_with(remaining) => {
+                    // `prefix` ends partway through this edge; the node we land on is
+                    // keyed by the whole edge label, not just the requested prefix.
+                    path.extend_from_slice(&edge.label);
+                    current = &edge.target;
+                    remaining = &[];
+                }
+                Some(edge) if remaining.starts_with(edge.label.as_slice()) => {
+                    path.extend_from_slice(&edge.label);
+                    remaining = &remaining[edge.label.len()..];
+                    current = &edge.target;
+                }
+                _ => return result,
+            }
+        }
+
+        Self::collect_subtree(current, path, &mut result);
+        result
+    }
+
+    /// Like [`Trie::keys_with_prefix`], but for the common case of UTF-8 keys: each key is
+    /// decoded to a `String` instead of being left as raw bytes. Panics if any stored key
+    /// under `prefix` is not valid UTF-8.
+    pub fn keys_with_prefix_str<K: TrieKey + ?Sized>(&self, prefix: &K) -> Vec<(String, V)> {
+        self.keys_with_prefix(prefix)
+            .into_iter()
+            .map(|(key, value)| {
+                (
+                    String::from_utf8(key).expect("key is not valid UTF-8"),
+                    value,
+                )
+            })
+            .collect()
+    }
+
+    fn collect_subtree(node: &Arc<Node<V>>, path: Vec<u8>, result: &mut Vec<(Vec<u8>, V)>) {
+        if let Some(value) = &node.value {
+            result.push((path.clone(), value.clone()));
+        }
+        for edge in node.children.values() {
+            let mut child_path = path.clone();
+            child_path.extend_from_slice(&edge.label);
+            Self::collect_subtree(&edge.target, child_path, result);
+        }
+    }
+
+    /// Returns this snapshot's root Merkle hash. Two tries with the same `root_hash` are
+    /// guaranteed to hold identical key/value pairs; the empty trie hashes to all-zero.
+    pub fn root_hash(&self) -> [u8; 32] {
+        match &self.root {
+            Some(root) => root.hash,
+            None => [0u8; 32],
         }
     }
 
-    pub fn get_root(&self) -> Arc<Node> {
+    /// O(1) structural-equality check: two snapshots are equal iff their root hashes
+    /// match, since the COW structure guarantees identical content hashes the same way.
+    pub fn structurally_eq(&self, other: &Trie<V>) -> bool {
+        self.root_hash() == other.root_hash()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<V: serde::Serialize + serde::de::DeserializeOwned> Trie<V> {
+    /// Serializes this snapshot to a JSON byte buffer, suitable for writing to disk or
+    /// shipping across a network. The whole `Arc<Node>` tree is flattened into owned data;
+    /// reloading it with [`Trie::from_bytes`] rebuilds fresh `Arc`s rather than restoring
+    /// any sharing the original snapshot had with others.
+    pub fn to_bytes(&self) -> serde_json::Result<Vec<u8>> {
+        serde_json::to_vec(self)
+    }
+
+    /// Reconstructs a snapshot previously produced by [`Trie::to_bytes`].
+    pub fn from_bytes(bytes: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(bytes)
+    }
+}
+
+impl<V> Trie<V> {
+    pub fn get_root(&self) -> Arc<Node<V>> {
         self.root.clone().unwrap_or_else(|| Arc::new(Node::new()))
     }
 
     /// Create a new Trie from a root node
-    pub fn from_node(root: Arc<Node>) -> Self {
-        Trie { root: Some(root) }
+    pub fn from_node(root: Arc<Node<V>>) -> Self {
+        Trie {
+            root: Some(root),
+            allocator: None,
+        }
     }
 
     /// Extract the root node from the Trie
-    pub fn into_node(self) -> Arc<Node> {
+    pub fn into_node(self) -> Arc<Node<V>> {
         self.root.unwrap_or_else(|| Arc::new(Node::new()))
     }
 }
 
+/// One level of an in-progress [`Iter`] traversal: the accumulated key up to this node,
+/// the node's own value (taken and emitted the first time this frame is visited), and its
+/// remaining children still to descend into, in sorted edge-byte order.
+struct Frame<V> {
+    path: Vec<u8>,
+    value: Option<V>,
+    children: std::vec::IntoIter<Edge<V>>,
+}
+
+impl<V> Frame<V> {
+    fn new(path: Vec<u8>, node: &Node<V>) -> Self
+    where
+        V: Clone,
+    {
+        let mut edges: Vec<(&u8, &Edge<V>)> = node.children.iter().collect();
+        edges.sort_by_key(|(first_byte, _)| **first_byte);
+        let children: Vec<Edge<V>> = edges.into_iter().map(|(_, edge)| edge.clone()).collect();
+
+        Frame {
+            path,
+            value: node.value.clone(),
+            children: children.into_iter(),
+        }
+    }
+}
+
+/// Non-recursive, explicit-stack depth-first iterator over every key/value pair in a
+/// [`Trie`], so traversing a snapshot built from very long keys can't blow the call stack.
+pub struct Iter<V> {
+    stack: Vec<Frame<V>>,
+}
+
+impl<V: Clone> Iterator for Iter<V> {
+    type Item = (Vec<u8>, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let frame = self.stack.last_mut()?;
+
+            if let Some(value) = frame.value.take() {
+                return Some((frame.path.clone(), value));
+            }
+
+            match frame.children.next() {
+                Some(edge) => {
+                    let mut path = frame.path.clone();
+                    path.extend_from_slice(&edge.label);
+                    self.stack.push(Frame::new(path, &edge.target));
+                }
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+impl<V: Clone> Trie<V> {
+    /// Returns a non-recursive depth-first iterator over every `(key, value)` pair
+    /// currently stored, in sorted-edge-byte order.
+    pub fn iter(&self) -> Iter<V> {
+        let stack = match &self.root {
+            Some(root) => vec![Frame::new(Vec::new(), root)],
+            None => Vec::new(),
+        };
+        Iter { stack }
+    }
+
+    /// Returns an iterator over every stored key, in the same order as [`Trie::iter`].
+    pub fn keys(&self) -> impl Iterator<Item = Vec<u8>> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    /// Like [`Trie::iter`], but for the common case of UTF-8 keys: each key is decoded to
+    /// a `String` instead of being left as raw bytes. Panics if any stored key is not
+    /// valid UTF-8.
+    pub fn iter_str(&self) -> impl Iterator<Item = (String, V)> {
+        self.iter().map(|(key, value)| {
+            (
+                String::from_utf8(key).expect("key is not valid UTF-8"),
+                value,
+            )
+        })
+    }
+
+    /// Like [`Trie::keys`], but decodes each key to a `String`. Panics if any stored key
+    /// is not valid UTF-8.
+    pub fn keys_str(&self) -> impl Iterator<Item = String> {
+        self.iter_str().map(|(key, _)| key)
+    }
+
+    /// Returns an iterator over every stored value, in the same order as [`Trie::iter`].
+    pub fn values(&self) -> impl Iterator<Item = V> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn basic_put_test() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
         let trie = trie.put("test-int", 233u32);
         let trie = trie.put("test-int2", 23333333u64);
         let trie = trie.put("test-string", "test".to_string());
@@ -163,61 +614,22 @@ mod tests {
 
     #[test]
     fn trie_structure_check() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
         let trie = trie.put("test", 233u32);
         assert_eq!(trie.get("test"), Some(Value::Int32(233)));
 
-        // Ensure the trie structure matches expectations
+        // A single key collapses into one compressed edge, not a chain of per-byte nodes.
         let root = trie.get_root();
         assert_eq!(root.children.len(), 1);
-        assert_eq!(root.children.get(&'t').unwrap().children.len(), 1);
-        assert_eq!(
-            root.children
-                .get(&'t')
-                .unwrap()
-                .children
-                .get(&'e')
-                .unwrap()
-                .children
-                .len(),
-            1
-        );
-        assert_eq!(
-            root.children
-                .get(&'t')
-                .unwrap()
-                .children
-                .get(&'e')
-                .unwrap()
-                .children
-                .get(&'s')
-                .unwrap()
-                .children
-                .len(),
-            1
-        );
-        assert_eq!(
-            root.children
-                .get(&'t')
-                .unwrap()
-                .children
-                .get(&'e')
-                .unwrap()
-                .children
-                .get(&'s')
-                .unwrap()
-                .children
-                .get(&'t')
-                .unwrap()
-                .children
-                .len(),
-            0
-        );
+        let edge = root.children.get(&b't').unwrap();
+        assert_eq!(edge.label, b"test");
+        assert_eq!(edge.target.value, Some(Value::Int32(233)));
+        assert_eq!(edge.target.children.len(), 0);
     }
 
     #[test]
     fn basic_put_get_test() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
 
         // Put something
         let trie = trie.put("test", Value::Int32(233));
@@ -244,7 +656,7 @@ mod tests {
 
     #[test]
     fn put_get_one_path() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
 
         let trie = trie.put("111", Value::Int32(111));
         let trie = trie.put("11", Value::Int32(11));
@@ -258,7 +670,7 @@ mod tests {
 
     #[test]
     fn basic_delete_test1() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
 
         // Put something
         let trie = trie.put("test", Value::Int32(2333));
@@ -282,7 +694,7 @@ mod tests {
 
     #[test]
     fn basic_delete_test2() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
 
         // Put something
         let trie = trie.put("test", Value::Int32(2333));
@@ -311,7 +723,7 @@ mod tests {
 
     #[test]
     fn delete_free_test() {
-        let trie = Trie::new();
+        let trie = Trie::<Value>::new();
 
         let trie = trie.put("test", Value::Int32(2333));
         let trie = trie.put("te", Value::Int32(23));
@@ -320,14 +732,10 @@ mod tests {
         let trie = trie.delete("tes");
         let trie = trie.delete("test");
 
-        assert_eq!(
-            trie.get_root()
-                .children
-                .get(&'t')
-                .and_then(|child| child.children.get(&'e'))
-                .map(|child| child.children.len()),
-            Some(0)
-        );
+        let edge = trie.get_root().children.get(&b't').unwrap().clone();
+        assert_eq!(edge.label, b"te");
+        assert_eq!(edge.target.value, Some(Value::Int32(23)));
+        assert_eq!(edge.target.children.len(), 0);
 
         let trie = trie.delete("te");
         assert_eq!(trie.get_root(), Arc::new(Node::new()));
@@ -335,7 +743,7 @@ mod tests {
 
     #[test]
     fn copy_on_write_test1() {
-        let empty_trie = Trie::new();
+        let empty_trie = Trie::<Value>::new();
 
         // Put something
         let trie1 = empty_trie.put("test", Value::Int32(2333));
@@ -367,7 +775,7 @@ mod tests {
 
     #[test]
     fn copy_on_write_test2() {
-        let empty_trie = Trie::new();
+        let empty_trie = Trie::<Value>::new();
 
         // Put something
         let trie1 = empty_trie.put("test", Value::Int32(2333));
@@ -399,7 +807,7 @@ mod tests {
 
     #[test]
     fn copy_on_write_test3() {
-        let empty_trie = Trie::new();
+        let empty_trie = Trie::<Value>::new();
 
         // Put something
         let trie1 = empty_trie.put("test", Value::Int32(2333));
@@ -429,9 +837,275 @@ mod tests {
         assert_eq!(trie6.get("test"), Some(Value::String("2333".to_string())));
     }
 
+    #[test]
+    fn find_prefixes_test() {
+        let trie = Trie::<Value>::new();
+        let trie = trie.put("a", Value::Int32(1));
+        let trie = trie.put("ab", Value::Int32(2));
+        let trie = trie.put("abcd", Value::Int32(4));
+
+        assert_eq!(
+            trie.find_prefixes("abcde"),
+            vec![Value::Int32(1), Value::Int32(2), Value::Int32(4)]
+        );
+        assert_eq!(
+            trie.find_prefixes("ab"),
+            vec![Value::Int32(1), Value::Int32(2)]
+        );
+        assert_eq!(trie.find_prefixes("xyz"), Vec::new());
+        assert_eq!(trie.find_prefixes(""), Vec::new());
+    }
+
+    #[test]
+    fn find_longest_prefix_test() {
+        let trie = Trie::<Value>::new();
+        let trie = trie.put("a", Value::Int32(1));
+        let trie = trie.put("ab", Value::Int32(2));
+        let trie = trie.put("abcd", Value::Int32(4));
+
+        assert_eq!(trie.find_longest_prefix("abcde"), Some(Value::Int32(4)));
+        assert_eq!(trie.find_longest_prefix("abc"), Some(Value::Int32(2)));
+        assert_eq!(trie.find_longest_prefix("a"), Some(Value::Int32(1)));
+        assert_eq!(trie.find_longest_prefix("xyz"), None);
+    }
+
+    #[test]
+    fn keys_with_prefix_test() {
+        let trie = Trie::<Value>::new();
+        let trie = trie.put("apple", Value::Int32(1));
+        let trie = trie.put("app", Value::Int32(2));
+        let trie = trie.put("apply", Value::Int32(3));
+        let trie = trie.put("banana", Value::Int32(4));
+
+        let mut result = trie.keys_with_prefix("app");
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            result,
+            vec![
+                (b"app".to_vec(), Value::Int32(2)),
+                (b"apple".to_vec(), Value::Int32(1)),
+                (b"apply".to_vec(), Value::Int32(3)),
+            ]
+        );
+
+        assert_eq!(
+            trie.keys_with_prefix("ban"),
+            vec![(b"banana".to_vec(), Value::Int32(4))]
+        );
+        assert_eq!(trie.keys_with_prefix("xyz"), Vec::new());
+    }
+
+    #[test]
+    fn keys_with_prefix_str_test() {
+        let trie = Trie::<Value>::new();
+        let trie = trie.put("apple", Value::Int32(1));
+        let trie = trie.put("app", Value::Int32(2));
+
+        let mut result = trie.keys_with_prefix_str("app");
+        result.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            result,
+            vec![
+                ("app".to_string(), Value::Int32(2)),
+                ("apple".to_string(), Value::Int32(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn root_hash_test() {
+        let empty = Trie::<Value>::new();
+        assert_eq!(empty.root_hash(), [0u8; 32]);
+
+        let trie1 = empty.put("test", Value::Int32(233));
+        let trie2 = empty.put("test", Value::Int32(233));
+        assert_eq!(trie1.root_hash(), trie2.root_hash());
+        assert!(trie1.structurally_eq(&trie2));
+
+        let trie3 = trie1.put("test", Value::Int32(234));
+        assert_ne!(trie1.root_hash(), trie3.root_hash());
+        assert!(!trie1.structurally_eq(&trie3));
+
+        // Deleting the only key brings the hash back to the empty-trie value.
+        let trie4 = trie1.delete("test");
+        assert_eq!(trie4.root_hash(), empty.root_hash());
+    }
+
+    #[test]
+    fn root_hash_shares_unchanged_subtrees() {
+        let trie1 = Trie::<Value>::new()
+            .put("apple", Value::Int32(1))
+            .put("apply", Value::Int32(2));
+        let trie2 = trie1.put("banana", Value::Int32(3));
+
+        // The "apple" subtree is untouched by adding "banana", so its hash is unchanged.
+        let apple_hash_before = trie1.get_root().children.get(&b'a').unwrap().target.hash;
+        let apple_hash_after = trie2.get_root().children.get(&b'a').unwrap().target.hash;
+        assert_eq!(apple_hash_before, apple_hash_after);
+        assert_ne!(trie1.root_hash(), trie2.root_hash());
+    }
+
+    #[test]
+    fn iter_test() {
+        let trie = Trie::<Value>::new()
+            .put("apple", Value::Int32(1))
+            .put("app", Value::Int32(2))
+            .put("apply", Value::Int32(3))
+            .put("banana", Value::Int32(4));
+
+        let mut pairs = trie.iter().collect::<Vec<_>>();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            pairs,
+            vec![
+                (b"app".to_vec(), Value::Int32(2)),
+                (b"apple".to_vec(), Value::Int32(1)),
+                (b"apply".to_vec(), Value::Int32(3)),
+                (b"banana".to_vec(), Value::Int32(4)),
+            ]
+        );
+
+        let mut keys = trie.keys().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(
+            keys,
+            vec![
+                b"app".to_vec(),
+                b"apple".to_vec(),
+                b"apply".to_vec(),
+                b"banana".to_vec()
+            ]
+        );
+
+        let mut values = trie.values().collect::<Vec<_>>();
+        values.sort_by_key(|v| match v {
+            Value::Int32(v) => *v,
+            _ => unreachable!(),
+        });
+        assert_eq!(
+            values,
+            vec![
+                Value::Int32(1),
+                Value::Int32(2),
+                Value::Int32(3),
+                Value::Int32(4),
+            ]
+        );
+
+        assert_eq!(Trie::<Value>::new().iter().next(), None);
+    }
+
+    #[test]
+    fn iter_str_test() {
+        let trie = Trie::<Value>::new()
+            .put("apple", Value::Int32(1))
+            .put("app", Value::Int32(2));
+
+        let mut pairs = trie.iter_str().collect::<Vec<_>>();
+        pairs.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            pairs,
+            vec![
+                ("app".to_string(), Value::Int32(2)),
+                ("apple".to_string(), Value::Int32(1)),
+            ]
+        );
+
+        let mut keys = trie.keys_str().collect::<Vec<_>>();
+        keys.sort();
+        assert_eq!(keys, vec!["app".to_string(), "apple".to_string()]);
+    }
+
+    #[test]
+    fn interning_shares_identical_subtrees_test() {
+        let trie1 = Trie::<Value>::with_interning()
+            .put("apple", Value::Int32(1))
+            .put("apply", Value::Int32(2));
+        let trie2 = Trie::<Value>::with_interning()
+            .put("apple", Value::Int32(1))
+            .put("apply", Value::Int32(2));
+
+        // Built independently, but identical in content: the interner collapses both
+        // roots to the same underlying allocation.
+        assert!(Arc::ptr_eq(&trie1.get_root(), &trie2.get_root()));
+
+        // Without interning, an otherwise-identical trie gets its own allocation.
+        let trie3 = Trie::<Value>::new()
+            .put("apple", Value::Int32(1))
+            .put("apply", Value::Int32(2));
+        assert!(trie1.structurally_eq(&trie3));
+        assert!(!Arc::ptr_eq(&trie1.get_root(), &trie3.get_root()));
+    }
+
+    #[test]
+    fn generic_value_and_binary_key_test() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Point {
+            x: i32,
+            y: i32,
+        }
+
+        impl HashableValue for Point {
+            fn hash_bytes(&self) -> Vec<u8> {
+                let mut bytes = self.x.to_le_bytes().to_vec();
+                bytes.extend_from_slice(&self.y.to_le_bytes());
+                bytes
+            }
+        }
+
+        let trie: Trie<Point> = Trie::new();
+        let key: &[u8] = &[0x01, 0x02, 0x03];
+        let trie = trie.put(key, Point { x: 1, y: 2 });
+
+        assert_eq!(trie.get(key), Some(Point { x: 1, y: 2 }));
+        assert_eq!(trie.get([0x01, 0x02, 0x04].as_slice()), None);
+        assert_ne!(trie.root_hash(), [0u8; 32]);
+    }
+
+    #[test]
+    fn non_send_sync_value_test() {
+        use std::cell::RefCell;
+        use std::rc::Rc;
+
+        // `Rc<RefCell<_>>` is neither `Send` nor `Sync`; a plain (non-interning) `Trie`
+        // must still accept it, since only `with_interning` needs that bound.
+        #[derive(Clone)]
+        struct Counter(Rc<RefCell<u32>>);
+
+        impl HashableValue for Counter {
+            fn hash_bytes(&self) -> Vec<u8> {
+                self.0.borrow().to_le_bytes().to_vec()
+            }
+        }
+
+        let trie: Trie<Counter> = Trie::new();
+        let trie = trie.put("count", Counter(Rc::new(RefCell::new(1))));
+        assert_eq!(*trie.get("count").unwrap().0.borrow(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_round_trip_test() {
+        let trie = Trie::<Value>::new()
+            .put("apple", Value::Int32(1))
+            .put("apply", Value::Int32(2))
+            .put("banana", Value::String("yellow".to_string()));
+
+        let bytes = trie.to_bytes().unwrap();
+        let restored = Trie::<Value>::from_bytes(&bytes).unwrap();
+
+        assert!(trie.structurally_eq(&restored));
+        assert_eq!(restored.get("apple"), Some(Value::Int32(1)));
+        assert_eq!(restored.get("apply"), Some(Value::Int32(2)));
+        assert_eq!(
+            restored.get("banana"),
+            Some(Value::String("yellow".to_string()))
+        );
+    }
+
     #[test]
     fn mixed_test() {
-        let mut trie = Trie::new();
+        let mut trie = Trie::<Value>::new();
         let n = 23333;
         for i in 0..n {
             let key = format!("{:05}", i);
@@ -1,4 +1,5 @@
 #[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Value {
     Int32(u32),
     Int64(u64),
@@ -28,3 +29,27 @@ impl From<&str> for Value {
         Value::String(v.to_string())
     }
 }
+
+impl crate::node::HashableValue for Value {
+    /// Canonical byte encoding used for content-addressed hashing: a tag byte followed by
+    /// the value's bytes (little-endian for integers, UTF-8 for strings).
+    fn hash_bytes(&self) -> Vec<u8> {
+        match self {
+            Value::Int32(v) => {
+                let mut bytes = vec![0u8];
+                bytes.extend_from_slice(&v.to_le_bytes());
+                bytes
+            }
+            Value::Int64(v) => {
+                let mut bytes = vec![1u8];
+                bytes.extend_from_slice(&v.to_le_bytes());
+                bytes
+            }
+            Value::String(s) => {
+                let mut bytes = vec![2u8];
+                bytes.extend_from_slice(s.as_bytes());
+                bytes
+            }
+        }
+    }
+}